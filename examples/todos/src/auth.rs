@@ -0,0 +1,81 @@
+use axum::{
+    extract::{Extension, TypedHeader},
+    headers::{authorization::Bearer, Authorization},
+    http::Request,
+    middleware::Next,
+    response::IntoResponse,
+    Json,
+};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::error::AppError;
+
+/// Claims encoded into (and decoded from) the bearer JWT.
+///
+/// `sub` carries the authenticated username and `exp` the expiry, in
+/// seconds since the epoch, that `jsonwebtoken` validates against.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub exp: i64,
+}
+
+/// `axum::middleware::from_fn` handler that requires a valid
+/// `Authorization: Bearer <jwt>` header, short-circuiting with `401` when
+/// it is missing or the token fails to validate.
+pub async fn require_auth<B>(
+    Extension(config): Extension<Config>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Result<impl IntoResponse, AppError> {
+    let TypedHeader(auth) = auth.ok_or(AppError::Unauthorized)?;
+
+    let claims = decode::<Claims>(
+        auth.token(),
+        &DecodingKey::from_secret(config.jwt_secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map_err(|_| AppError::Unauthorized)?
+    .claims;
+
+    request.extensions_mut().insert(claims);
+
+    Ok(next.run(request).await)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginInput {
+    pub username: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoginResponse {
+    pub token: String,
+}
+
+/// `POST /login` — issues a bearer token for the given username.
+///
+/// This example has no real user store, so any username is accepted; a
+/// production service would verify credentials before signing a token.
+pub async fn login(
+    Extension(config): Extension<Config>,
+    Json(input): Json<LoginInput>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let now = chrono::Utc::now();
+    let claims = Claims {
+        sub: input.username,
+        exp: (now + chrono::Duration::seconds(config.jwt_maxage)).timestamp(),
+    };
+
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.jwt_secret.as_bytes()),
+    )
+    .map_err(|_| AppError::InvalidBody("failed to sign token".to_string()))?;
+
+    Ok(Json(LoginResponse { token }))
+}
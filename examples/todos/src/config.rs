@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+/// Output format for the `fmt` tracing layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable, for local development.
+    Text,
+    /// Newline-delimited JSON, for log aggregators.
+    Json,
+}
+
+/// Runtime configuration, populated from environment variables with
+/// sensible defaults so the server can be tuned for deployment without
+/// code changes.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// `postgres://...` or `sqlite://...` — the scheme picks the backend
+    /// at runtime via `sqlx::Any`.
+    pub database_url: String,
+    pub host: String,
+    pub port: u16,
+    pub max_connections: u32,
+    pub acquire_timeout: Duration,
+    pub idle_timeout: Duration,
+    pub request_timeout: Duration,
+    pub log_filter: String,
+    pub jwt_secret: String,
+    /// How long an issued token stays valid, in seconds.
+    pub jwt_maxage: i64,
+    pub log_format: LogFormat,
+    pub otel_enabled: bool,
+    pub service_name: String,
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        Self {
+            database_url: env_or(
+                "DATABASE_URL",
+                "postgres://postgres@localhost:5432/todos",
+            ),
+            host: env_or("HOST", "127.0.0.1"),
+            port: env_parse_or("PORT", 3000),
+            max_connections: env_parse_or("MAX_CONNECTIONS", default_max_connections()),
+            acquire_timeout: Duration::from_secs(env_parse_or("ACQUIRE_TIMEOUT_SECS", 10)),
+            idle_timeout: Duration::from_secs(env_parse_or("IDLE_TIMEOUT_SECS", 600)),
+            request_timeout: Duration::from_secs(env_parse_or("REQUEST_TIMEOUT_SECS", 10)),
+            log_filter: env_or("RUST_LOG", "example_todos=debug,tower_http=debug"),
+            jwt_secret: env_or("JWT_SECRET", "todos-example-dev-secret"),
+            jwt_maxage: env_parse_or("JWT_MAXAGE", 60 * 60),
+            log_format: if env_or("LOG_FORMAT", "text") == "json" {
+                LogFormat::Json
+            } else {
+                LogFormat::Text
+            },
+            otel_enabled: env_parse_or("OTEL_ENABLED", false),
+            service_name: env_or("OTEL_SERVICE_NAME", "todo-service"),
+        }
+    }
+}
+
+/// Default `max_connections` scales with the number of available CPUs,
+/// matching sqlx's own pooling guidance.
+fn default_max_connections() -> u32 {
+    std::thread::available_parallelism()
+        .map(|n| n.get() as u32 * 2)
+        .unwrap_or(10)
+}
+
+fn env_or(key: &str, default: &str) -> String {
+    std::env::var(key).unwrap_or_else(|_| default.to_string())
+}
+
+fn env_parse_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
@@ -1,84 +1,138 @@
-use sqlx::postgres::PgPool;
+use async_trait::async_trait;
+use sqlx::any::{AnyPool, AnyRow};
+use sqlx::{FromRow, Row};
 
+use crate::error::AppError;
 use crate::Pagination;
 use crate::Todo;
 
-pub async fn find_all_todos(pool: PgPool, pagination: Pagination) -> anyhow::Result<Vec<Todo>> {
-    let limit: i64 = pagination.limit.unwrap_or(i64::MAX);
-    let offset: i64 = pagination.offset.unwrap_or(0);
-    let todos = sqlx::query_as!(Todo,
-        r#"
-SELECT id, text, completed
-FROM todos
-LIMIT $1
-OFFSET $2
-        "#,
-        limit,
-        offset,
-        )
-        .fetch_all(&pool)
-        .await?;
+/// `sqlx::Any` has no `Decode`/`Encode` for `uuid::Uuid` (its row model
+/// only covers Bool/SmallInt/Integer/BigInt/Real/Double/Text/Blob), so
+/// the `todos.id` column is `TEXT` on every backend (see
+/// `migrations/0001_create_todos.sql`) and is parsed back into a `Uuid`
+/// here, mirroring the `.bind(id.to_string())` calls below.
+impl FromRow<'_, AnyRow> for Todo {
+    fn from_row(row: &AnyRow) -> sqlx::Result<Self> {
+        let id: String = row.try_get("id")?;
+        let id = uuid::Uuid::parse_str(&id).map_err(|error| sqlx::Error::ColumnDecode {
+            index: "id".to_string(),
+            source: Box::new(error),
+        })?;
 
-    Ok(todos)
+        Ok(Todo {
+            id,
+            text: row.try_get("text")?,
+            completed: row.try_get("completed")?,
+        })
+    }
 }
 
-pub async fn find_one_todo(pool: &PgPool, id: uuid::Uuid) -> anyhow::Result<Option<Todo>> {
-    let todo = sqlx::query_as!(Todo,
-        r#"
-SELECT id, text, completed
-FROM todos
-WHERE id = $1
-       "#,
-       id,
-       )
-        .fetch_optional(pool)
-        .await?;
-    Ok(todo)
+/// Storage abstraction implemented by each supported backend.
+///
+/// The todo handlers depend only on this trait (via `Extension<Arc<dyn
+/// Repository>>`), not on a concrete pool type, so the same binary can
+/// target Postgres or SQLite depending on the `DATABASE_URL` scheme.
+#[async_trait]
+pub trait Repository: Send + Sync {
+    async fn find_all(&self, pagination: Pagination) -> Result<(Vec<Todo>, i64), AppError>;
+    async fn find_one(&self, id: uuid::Uuid) -> Result<Option<Todo>, AppError>;
+    async fn insert(&self, todo: Todo) -> Result<Todo, AppError>;
+    async fn update(&self, todo: Todo) -> Result<Todo, AppError>;
+    async fn delete(&self, id: uuid::Uuid) -> Result<Option<uuid::Uuid>, AppError>;
 }
 
-pub async fn insert_todo(pool: PgPool, todo: Todo) -> anyhow::Result<Todo> {
-    let todo = sqlx::query_as!(Todo,
-        r#"
-INSERT INTO todos (id, text, completed)
-VALUES ($1, $2, $3)
-RETURNING id, text, completed
-       "#,
-       todo.id,
-       todo.text,
-       todo.completed,
-       )
-        .fetch_one(&pool)
-        .await?;
-    Ok(todo)
+/// `Repository` implementation backed by `sqlx::Any`, so the backend
+/// (Postgres or SQLite) is chosen at runtime by the `DATABASE_URL` scheme.
+///
+/// Queries here use the runtime `query_as`/`query` API rather than the
+/// `query_as!` macros, since those require a live database at compile
+/// time for a single, fixed driver.
+pub struct AnyRepository {
+    pool: AnyPool,
 }
 
-pub async fn update_todo(pool: PgPool, todo: Todo) -> anyhow::Result<Todo> {
-    let todo = sqlx::query_as!(Todo,
-        r#"
-UPDATE todos SET
-  text = $2,
-  completed = $3
-WHERE id = $1
-RETURNING id, text, completed
-       "#,
-       todo.id,
-       todo.text,
-       todo.completed,
-       )
-        .fetch_one(&pool)
-        .await?;
-    Ok(todo)
+impl AnyRepository {
+    pub fn new(pool: AnyPool) -> Self {
+        Self { pool }
+    }
 }
 
-pub async fn delete_todo(pool: PgPool, id: uuid::Uuid) -> anyhow::Result<Option<uuid::Uuid>> {
-    let delete_count = sqlx::query!("DELETE FROM todos WHERE id = $1", id)
-        .execute(&pool)
+#[async_trait]
+impl Repository for AnyRepository {
+    async fn find_all(&self, pagination: Pagination) -> Result<(Vec<Todo>, i64), AppError> {
+        let page_size: i64 = pagination.page_size() as i64;
+        let offset: i64 = (pagination.page() as i64 - 1) * page_size;
+
+        let todos = sqlx::query_as::<_, Todo>(
+            "SELECT id, text, completed FROM todos LIMIT $1 OFFSET $2",
+        )
+        .bind(page_size)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total: i64 = sqlx::query("SELECT COUNT(*) AS count FROM todos")
+            .fetch_one(&self.pool)
+            .await?
+            .try_get("count")?;
+
+        Ok((todos, total))
+    }
+
+    async fn find_one(&self, id: uuid::Uuid) -> Result<Option<Todo>, AppError> {
+        let todo = sqlx::query_as::<_, Todo>("SELECT id, text, completed FROM todos WHERE id = $1")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(todo)
+    }
+
+    async fn insert(&self, todo: Todo) -> Result<Todo, AppError> {
+        let todo = sqlx::query_as::<_, Todo>(
+            "INSERT INTO todos (id, text, completed) VALUES ($1, $2, $3) RETURNING id, text, completed",
+        )
+        .bind(todo.id.to_string())
+        .bind(todo.text)
+        .bind(todo.completed)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(todo)
+    }
+
+    async fn update(&self, todo: Todo) -> Result<Todo, AppError> {
+        let todo = sqlx::query_as::<_, Todo>(
+            "UPDATE todos SET text = $2, completed = $3 WHERE id = $1 RETURNING id, text, completed",
+        )
+        .bind(todo.id.to_string())
+        .bind(todo.text)
+        .bind(todo.completed)
+        .fetch_optional(&self.pool)
         .await?
-        .rows_affected();
+        .ok_or(AppError::NotFound)?;
 
-    if delete_count > 0 {
-        Ok(Some(id))
-    } else {
-        Ok(None)
+        Ok(todo)
     }
+
+    async fn delete(&self, id: uuid::Uuid) -> Result<Option<uuid::Uuid>, AppError> {
+        let delete_count = sqlx::query("DELETE FROM todos WHERE id = $1")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        if delete_count > 0 {
+            Ok(Some(id))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Runs a trivial query against the pool so callers can distinguish
+/// "process up" from "database reachable".
+pub async fn ping(pool: &AnyPool) -> Result<(), AppError> {
+    sqlx::query("SELECT 1").execute(pool).await?;
+    Ok(())
 }
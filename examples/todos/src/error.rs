@@ -0,0 +1,48 @@
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde::Serialize;
+
+/// The crate's top-level error type.
+///
+/// Handlers return `Result<_, AppError>` so a failed query turns into a
+/// clean HTTP response instead of a panic.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("todo not found")]
+    NotFound,
+
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("invalid request body: {0}")]
+    InvalidBody(String),
+
+    #[error("missing or invalid bearer token")]
+    Unauthorized,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match &self {
+            AppError::NotFound => StatusCode::NOT_FOUND,
+            AppError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::InvalidBody(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+        };
+
+        // Don't leak database internals (connection info, SQL state,
+        // constraint names, ...) to the client; log it server-side instead.
+        let message = if let AppError::Database(ref error) = self {
+            tracing::error!(%error, "database error");
+            "internal server error".to_string()
+        } else {
+            self.to_string()
+        };
+
+        (status, Json(ErrorBody { error: message })).into_response()
+    }
+}
@@ -17,44 +17,66 @@ use axum::{
     error_handling::HandleErrorLayer,
     extract::{Extension, Path, Query},
     http::StatusCode,
+    middleware::from_fn,
     response::IntoResponse,
-    routing::{get, patch},
+    routing::{get, patch, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::postgres::PgPool;
-use std::{
-    net::SocketAddr,
-    time::Duration,
-};
+use sqlx::any::{AnyPool, AnyPoolOptions};
+use std::net::SocketAddr;
+use std::sync::Arc;
 use tower::{BoxError, ServiceBuilder};
 use tower_http::{add_extension::AddExtensionLayer, trace::TraceLayer};
 use tracing::{event, Level};
-use tracing_subscriber::{fmt::format::FmtSpan, layer::SubscriberExt, EnvFilter};
+use tracing_subscriber::{fmt::format::FmtSpan, layer::SubscriberExt, EnvFilter, Layer};
 use uuid::Uuid;
 
+pub mod auth;
+pub mod config;
 pub mod db;
+pub mod error;
+
+use auth::require_auth;
+use config::{Config, LogFormat};
+use db::Repository;
+use error::AppError;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let config = Config::from_env();
+
     // Set the RUST_LOG, if it hasn't been explicitly defined
     if std::env::var_os("RUST_LOG").is_none() {
-        std::env::set_var("RUST_LOG", "example_todos=debug,tower_http=debug")
+        std::env::set_var("RUST_LOG", &config.log_filter)
     }
 
-    init_tracing()?;
+    init_tracing(&config)?;
+
+    sqlx::any::install_default_drivers();
 
-    let db_url = std::env::var_os("DATABASE_URL")
-        .unwrap_or_else(|| std::ffi::OsString::from("postgres://postgres@localhost:5432/todos"))
-        .into_string()
-        .map_err(|_| anyhow::anyhow!("DATABASE_URL is malformed"))?;
+    let pool = AnyPoolOptions::new()
+        .max_connections(config.max_connections)
+        .acquire_timeout(config.acquire_timeout)
+        .idle_timeout(config.idle_timeout)
+        .connect(&config.database_url)
+        .await?;
 
-    let pool = PgPool::connect(db_url.as_str()).await?;
+    let repo: Arc<dyn Repository> = Arc::new(db::AnyRepository::new(pool.clone()));
+
+    // Mutating routes require a valid bearer token
+    let protected_routes = Router::new()
+        .route("/todos", post(todos_create))
+        .route("/todos/:id", patch(todos_update).delete(todos_delete))
+        .layer(ServiceBuilder::new().layer(from_fn(require_auth)));
 
     // Compose the routes
     let app = Router::new()
-        .route("/todos", get(todos_index).post(todos_create))
-        .route("/todos/:id", patch(todos_update).delete(todos_delete))
+        .route("/todos", get(todos_index))
+        .route("/login", post(auth::login))
+        .route("/health", get(health))
+        .route("/health/db", get(health_db))
+        .merge(protected_routes)
         // Add middleware to all routes
         .layer(
             ServiceBuilder::new()
@@ -68,13 +90,21 @@ async fn main() -> anyhow::Result<()> {
                         ))
                     }
                 }))
-                .timeout(Duration::from_secs(10))
+                .timeout(config.request_timeout)
                 .layer(TraceLayer::new_for_http())
                 .layer(AddExtensionLayer::new(pool))
+                .layer(AddExtensionLayer::new(repo))
+                .layer(AddExtensionLayer::new(config.clone()))
                 .into_inner(),
         );
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
+    let addr = SocketAddr::from((
+        config
+            .host
+            .parse::<std::net::IpAddr>()
+            .unwrap_or_else(|_| [127, 0, 0, 1].into()),
+        config.port,
+    ));
     tracing::debug!("listening on {}", addr);
     axum::Server::bind(&addr)
         .serve(app.into_make_service())
@@ -83,25 +113,82 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Liveness probe: if the process can respond at all, it's up.
+async fn health() -> impl IntoResponse {
+    StatusCode::OK
+}
+
+#[derive(Serialize)]
+struct HealthDbError {
+    status: &'static str,
+    reason: String,
+}
+
+/// Readiness probe: confirms the database is actually reachable, so
+/// orchestrators can tell "process up" apart from "database reachable".
+async fn health_db(Extension(pool): Extension<AnyPool>) -> impl IntoResponse {
+    match db::ping(&pool).await {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(error) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(HealthDbError {
+                status: "unavailable",
+                reason: error.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+const DEFAULT_PAGE_SIZE: u32 = 20;
+const MAX_PAGE_SIZE: u32 = 100;
+
 // The query parameters for todos index
 #[derive(Debug, Deserialize, Default)]
 pub struct Pagination {
-    pub offset: Option<i64>, // FIXME: should be unsigned
-    pub limit: Option<i64>,  // FIXME: should be unsigned
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+}
+
+impl Pagination {
+    pub(crate) fn page(&self) -> u32 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub(crate) fn page_size(&self) -> u32 {
+        self.page_size
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct TodosPage {
+    items: Vec<Todo>,
+    page: u32,
+    page_size: u32,
+    total: i64,
+    total_pages: i64,
 }
 
 async fn todos_index(
     pagination: Option<Query<Pagination>>,
-    Extension(pool): Extension<PgPool>,
-) -> impl IntoResponse {
+    Extension(repo): Extension<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
     let Query(pagination) = pagination.unwrap_or_default();
+    let (page, page_size) = (pagination.page(), pagination.page_size());
 
     event!(Level::INFO, "GET /todos");
-    let todos = db::find_all_todos(pool, pagination)
-        .await
-        .expect("`todo` table query failed"); // FIXME: use error result
-
-    Json(todos)
+    let (items, total) = repo.find_all(pagination).await?;
+    let total_pages = (total + page_size as i64 - 1) / page_size as i64;
+
+    Ok(Json(TodosPage {
+        items,
+        page,
+        page_size,
+        total,
+        total_pages,
+    }))
 }
 
 #[derive(Debug, Deserialize)]
@@ -111,19 +198,17 @@ struct CreateTodo {
 
 async fn todos_create(
     Json(input): Json<CreateTodo>,
-    Extension(pool): Extension<PgPool>,
-) -> impl IntoResponse {
+    Extension(repo): Extension<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
     let todo = Todo {
         id: Uuid::new_v4(),
         text: input.text,
         completed: false,
     };
 
-    db::insert_todo(pool, todo.clone())
-        .await
-        .expect("`todo` table insert failed"); // FIXME: use error result
+    let todo = repo.insert(todo).await?;
 
-    (StatusCode::CREATED, Json(todo))
+    Ok((StatusCode::CREATED, Json(todo)))
 }
 
 #[derive(Debug, Deserialize)]
@@ -135,12 +220,9 @@ struct UpdateTodo {
 async fn todos_update(
     Path(id): Path<Uuid>,
     Json(input): Json<UpdateTodo>,
-    Extension(pool): Extension<PgPool>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let mut todo = db::find_one_todo(&pool, id)
-        .await
-        .expect("FIXME: ")
-        .ok_or(StatusCode::NOT_FOUND)?;
+    Extension(repo): Extension<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut todo = repo.find_one(id).await?.ok_or(AppError::NotFound)?;
 
     if let Some(text) = input.text {
         todo.text = text;
@@ -150,35 +232,48 @@ async fn todos_update(
         todo.completed = completed;
     }
 
-    let todo = db::update_todo(pool, todo)
-        .await
-        .expect("FIXME: ");
+    let todo = repo.update(todo).await?;
 
     Ok(Json(todo))
 }
 
-async fn todos_delete(Path(id): Path<Uuid>, Extension(pool): Extension<PgPool>) -> impl IntoResponse {
-    let deleted = db::delete_todo(pool, id)
-        .await
-        .expect("`todo` table delete failed"); // FIXME: use error result
+async fn todos_delete(
+    Path(id): Path<Uuid>,
+    Extension(repo): Extension<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    let deleted = repo.delete(id).await?;
 
     if deleted.is_some() {
-        StatusCode::NO_CONTENT
+        Ok(StatusCode::NO_CONTENT)
     } else {
-        StatusCode::NOT_FOUND
+        Err(AppError::NotFound)
     }
 }
 
-fn init_tracing() -> anyhow::Result<()> {
-    let fmt_layer = tracing_subscriber::fmt::layer()
-        .with_span_events(FmtSpan::CLOSE);
+fn init_tracing(config: &Config) -> anyhow::Result<()> {
+    let fmt_layer = match config.log_format {
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_thread_names(true)
+            .with_span_events(FmtSpan::CLOSE)
+            .boxed(),
+        LogFormat::Text => tracing_subscriber::fmt::layer()
+            .with_thread_names(true)
+            .with_span_events(FmtSpan::CLOSE)
+            .boxed(),
+    };
 
-    let jaeger_tracer = opentelemetry_jaeger::new_pipeline()
-        .with_service_name("todo-service")
-        .install_simple()?;
+    // The Jaeger/OpenTelemetry pipeline needs a collector to talk to, so
+    // it's opt-in rather than always-on.
+    let opentelemetry_layer = if config.otel_enabled {
+        let jaeger_tracer = opentelemetry_jaeger::new_pipeline()
+            .with_service_name(&config.service_name)
+            .install_simple()?;
 
-    let opentelemetry_layer = tracing_opentelemetry::layer()
-        .with_tracer(jaeger_tracer);
+        Some(tracing_opentelemetry::layer().with_tracer(jaeger_tracer))
+    } else {
+        None
+    };
 
     let filter_layer = EnvFilter::try_from_default_env()
         .or_else(|_| EnvFilter::try_new("info"))